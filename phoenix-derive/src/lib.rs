@@ -0,0 +1,410 @@
+//! `#[derive(ScimResource)]`: generates a SCIM `Schema` (and its `AttrType`
+//! tree) from a Rust struct's fields. `Type`, `multiValued` and `required`
+//! are inferred from the Rust field type; everything RFC 7643 needs that
+//! Rust can't express is supplied through `#[scim(...)]` helper attributes.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    bracketed, parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitStr,
+    PathArguments, Token, Type,
+};
+
+/// Parsed `#[scim(...)]` container attributes (on the struct itself).
+#[derive(Default)]
+struct ContainerAttrs {
+    schemaId: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// Parsed `#[scim(...)]` field attributes.
+#[derive(Default)]
+struct FieldAttrs {
+    mutability: Option<String>,
+    returned: Option<String>,
+    uniqueness: Option<String>,
+    caseExact: bool,
+    canonicalValues: Vec<String>,
+    referenceTypes: Vec<String>,
+    rename: Option<String>,
+}
+
+/// One entry inside `#[scim(...)]`: a bare word (`case_exact`), a single
+/// string value (`mutability = "readOnly"`), or a bracketed list of string
+/// values (`canonical_values = ["active", "disabled"]`).
+enum ScimEntry {
+    Flag(Ident),
+    Single(Ident, LitStr),
+    List(Ident, Vec<LitStr>),
+}
+
+impl Parse for ScimEntry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        if !input.peek(Token![=]) {
+            return Ok(ScimEntry::Flag(key));
+        }
+
+        input.parse::<Token![=]>()?;
+        if input.peek(syn::token::Bracket) {
+            let content;
+            bracketed!(content in input);
+            let values: Punctuated<LitStr, Token![,]> = Punctuated::parse_terminated(&content)?;
+            Ok(ScimEntry::List(key, values.into_iter().collect()))
+        } else {
+            Ok(ScimEntry::Single(key, input.parse()?))
+        }
+    }
+}
+
+/// Walks every `#[scim(...)]` attribute on `attrs`, collecting `key = "value"`,
+/// `key = ["value", ...]` and bare-word entries. A `#[scim(...)]` attribute
+/// that fails to parse adds one error to `errors` and is skipped, and the
+/// walk goes on with the next attribute rather than aborting (mirroring
+/// `serde_derive`).
+fn parseScimMeta(attrs: &[syn::Attribute], errors: &mut Vec<Error>) -> Vec<(String, Option<String>)> {
+    let mut out = Vec::new();
+
+    for attr in attrs {
+        if !attr.path.is_ident("scim") {
+            continue;
+        }
+
+        let entries = match attr.parse_args_with(Punctuated::<ScimEntry, Token![,]>::parse_terminated) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        for entry in entries {
+            match entry {
+                ScimEntry::Flag(key) => out.push((key.to_string(), None)),
+                ScimEntry::Single(key, value) => out.push((key.to_string(), Some(value.value()))),
+                ScimEntry::List(key, values) => {
+                    let key = key.to_string();
+                    for value in values {
+                        out.push((key.clone(), Some(value.value())));
+                    }
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn parseContainerAttrs(attrs: &[syn::Attribute], errors: &mut Vec<Error>) -> ContainerAttrs {
+    let mut out = ContainerAttrs::default();
+    for (key, value) in parseScimMeta(attrs, errors) {
+        match (key.as_str(), value) {
+            ("schema_id", Some(v)) => out.schemaId = Some(v),
+            ("name", Some(v)) => out.name = Some(v),
+            ("description", Some(v)) => out.description = Some(v),
+            (other, _) => errors.push(Error::new(
+                Span::call_site(),
+                format!("unknown container attribute `{}`", other),
+            )),
+        }
+    }
+    out
+}
+
+fn parseFieldAttrs(attrs: &[syn::Attribute], errors: &mut Vec<Error>) -> FieldAttrs {
+    let mut out = FieldAttrs::default();
+    for (key, value) in parseScimMeta(attrs, errors) {
+        match (key.as_str(), value) {
+            ("mutability", Some(v)) => out.mutability = Some(v),
+            ("returned", Some(v)) => out.returned = Some(v),
+            ("uniqueness", Some(v)) => out.uniqueness = Some(v),
+            ("case_exact", None) => out.caseExact = true,
+            ("canonical_values", Some(v)) => out.canonicalValues.push(v),
+            ("reference_types", Some(v)) => out.referenceTypes.push(v),
+            ("rename", Some(v)) => out.rename = Some(v),
+            (other, _) => errors.push(Error::new(
+                Span::call_site(),
+                format!("unknown field attribute `{}`", other),
+            )),
+        }
+    }
+    out
+}
+
+/// `true` for `Vec<u8>` specifically: that's the `binary` scalar, not a
+/// multi-valued `u8` attribute.
+fn isByteVec(ty: &Type) -> bool {
+    if let Type::Path(p) = ty {
+        if let Some(seg) = p.path.segments.last() {
+            if seg.ident == "Vec" {
+                if let PathArguments::AngleBracketed(args) = &seg.arguments {
+                    if let Some(GenericArgument::Type(Type::Path(inner))) = args.args.first() {
+                        return inner.path.is_ident("u8");
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Unwraps every nested `Option<Inner>` / `Vec<Inner>` layer, reporting whether
+/// the field is optional and/or multi-valued, and returns the innermost type.
+/// `Vec<u8>` is left alone rather than unwrapped, since it maps to the
+/// `binary` scalar rather than to a multi-valued attribute.
+fn unwrapFieldType(ty: &Type) -> (bool /* optional */, bool /* multiValued */, Type) {
+    if isByteVec(ty) {
+        return (false, false, ty.clone());
+    }
+
+    if let Type::Path(p) = ty {
+        if let Some(seg) = p.path.segments.last() {
+            if seg.ident == "Option" || seg.ident == "Vec" {
+                if let PathArguments::AngleBracketed(args) = &seg.arguments {
+                    if let Some(GenericArgument::Type(inner)) = args.args.first() {
+                        let (innerOptional, innerMulti, innermost) = unwrapFieldType(inner);
+                        return (
+                            innerOptional || seg.ident == "Option",
+                            innerMulti || seg.ident == "Vec",
+                            innermost,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    (false, false, ty.clone())
+}
+
+/// Maps an innermost Rust type to a SCIM `Type` string, `None` for types
+/// treated as `complex` (i.e. anything not in the known-scalar list).
+fn scimTypeOf(ty: &Type) -> Option<&'static str> {
+    if isByteVec(ty) {
+        return Some("binary");
+    }
+
+    let ident = match ty {
+        Type::Path(p) => p.path.segments.last()?.ident.to_string(),
+        _ => return None,
+    };
+
+    match ident.as_str() {
+        "String" => Some("string"),
+        "bool" => Some("boolean"),
+        "i64" | "i32" | "u32" | "u64" => Some("integer"),
+        "f64" | "f32" => Some("decimal"),
+        // chrono::DateTime<Utc> / chrono::NaiveDateTime, matched on the
+        // final path segment so either the fully-qualified or imported
+        // form works
+        "DateTime" | "NaiveDateTime" => Some("datetime"),
+        _ => None,
+    }
+}
+
+#[proc_macro_derive(ScimResource, attributes(scim))]
+pub fn derive_scim_resource(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let mut errors: Vec<Error> = Vec::new();
+
+    let container = parseContainerAttrs(&input.attrs, &mut errors);
+    let structIdent = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                errors.push(Error::new_spanned(
+                    structIdent,
+                    "ScimResource only supports structs with named fields",
+                ));
+                return combineErrors(errors);
+            }
+        },
+        _ => {
+            errors.push(Error::new_spanned(structIdent, "ScimResource can only be derived for structs"));
+            return combineErrors(errors);
+        }
+    };
+
+    let mut attrBuilders = Vec::new();
+    for field in fields.iter() {
+        let fieldAttrs = parseFieldAttrs(&field.attrs, &mut errors);
+        let fieldIdent = match &field.ident {
+            Some(ident) => ident,
+            None => continue,
+        };
+
+        let (optional, multiValued, innerTy) = unwrapFieldType(&field.ty);
+        let required = !optional;
+        let wireName = fieldAttrs.rename.clone().unwrap_or_else(|| fieldIdent.to_string());
+
+        let mutability = fieldAttrs.mutability.clone().unwrap_or_else(|| String::from("readWrite"));
+        let returned = fieldAttrs.returned.clone().unwrap_or_else(|| String::from("default"));
+        let uniqueness = fieldAttrs.uniqueness.clone().unwrap_or_else(|| String::from("none"));
+        let caseExact = fieldAttrs.caseExact;
+        let canonicalValues = fieldAttrs.canonicalValues.clone();
+        let referenceTypes = fieldAttrs.referenceTypes.clone();
+
+        let (scimType, subAttributesExpr) = match scimTypeOf(&innerTy) {
+            Some(scalarType) => (
+                quote! { String::from(#scalarType) },
+                quote! { ::std::vec::Vec::new() },
+            ),
+            None => (
+                quote! { String::from("complex") },
+                quote! { <#innerTy as ::schema::schema::schema::reflect::ScimResource>::__scim_sub_attributes() },
+            ),
+        };
+
+        attrBuilders.push(quote! {
+            ::std::rc::Rc::new(::schema::schema::schema::build_attr_type(
+                String::from(#wireName),
+                #scimType,
+                #required,
+                #multiValued,
+                String::from(#mutability),
+                String::from(#returned),
+                String::from(#uniqueness),
+                #caseExact,
+                vec![#(String::from(#canonicalValues)),*],
+                vec![#(String::from(#referenceTypes)),*],
+                #subAttributesExpr,
+            ))
+        });
+    }
+
+    if !errors.is_empty() {
+        return combineErrors(errors);
+    }
+
+    let schemaId = container.schemaId.unwrap_or_default();
+    let name = container.name.unwrap_or_else(|| structIdent.to_string());
+    let description = container.description.unwrap_or_default();
+
+    let expanded = quote! {
+        impl ::schema::schema::schema::reflect::ScimResource for #structIdent {
+            fn __scim_sub_attributes() -> ::std::vec::Vec<::std::rc::Rc<::schema::schema::schema::AttrType>> {
+                vec![#(#attrBuilders),*]
+            }
+
+            fn scim_schema() -> ::schema::schema::schema::Schema {
+                ::schema::schema::schema::Schema::from_parts(
+                    String::from(#schemaId),
+                    String::from(#name),
+                    String::from(#description),
+                    Self::__scim_sub_attributes(),
+                )
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+fn combineErrors(errors: Vec<Error>) -> TokenStream {
+    let mut iter = errors.into_iter();
+    let mut combined = iter.next().expect("combineErrors called with no errors");
+    for e in iter {
+        combined.combine(e);
+    }
+    TokenStream::from(combined.to_compile_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{Data, DeriveInput, Fields};
+
+    fn fieldType(rust_type: &str) -> Type {
+        let input: DeriveInput =
+            syn::parse_str(&format!("struct S {{ f: {} }}", rust_type)).unwrap();
+        match input.data {
+            Data::Struct(s) => match s.fields {
+                Fields::Named(named) => named.named.into_iter().next().unwrap().ty,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn fieldAttrs(src: &str) -> (Vec<syn::Attribute>, Vec<Error>) {
+        let input: DeriveInput =
+            syn::parse_str(&format!("struct S {{ {} f: String }}", src)).unwrap();
+        let field = match input.data {
+            Data::Struct(s) => match s.fields {
+                Fields::Named(named) => named.named.into_iter().next().unwrap(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        (field.attrs, Vec::new())
+    }
+
+    #[test]
+    fn isByteVec_recognizes_only_vec_of_u8() {
+        assert!(isByteVec(&fieldType("Vec<u8>")));
+        assert!(!isByteVec(&fieldType("Vec<String>")));
+        assert!(!isByteVec(&fieldType("u8")));
+    }
+
+    #[test]
+    fn unwrapFieldType_unwraps_option_of_vec() {
+        let (optional, multiValued, inner) = unwrapFieldType(&fieldType("Option<Vec<String>>"));
+        assert!(optional);
+        assert!(multiValued);
+        assert_eq!(scimTypeOf(&inner), Some("string"));
+    }
+
+    #[test]
+    fn unwrapFieldType_leaves_vec_u8_alone() {
+        let (optional, multiValued, inner) = unwrapFieldType(&fieldType("Vec<u8>"));
+        assert!(!optional);
+        assert!(!multiValued);
+        assert_eq!(scimTypeOf(&inner), Some("binary"));
+    }
+
+    #[test]
+    fn scimTypeOf_recognizes_datetime_and_unknown_types() {
+        assert_eq!(scimTypeOf(&fieldType("DateTime<Utc>")), Some("datetime"));
+        assert_eq!(scimTypeOf(&fieldType("NaiveDateTime")), Some("datetime"));
+        assert_eq!(scimTypeOf(&fieldType("MyComplexType")), None);
+    }
+
+    #[test]
+    fn parseFieldAttrs_collects_known_keys() {
+        let (attrs, mut errors) =
+            fieldAttrs(r#"#[scim(mutability = "readOnly", case_exact)]"#);
+        let parsed = parseFieldAttrs(&attrs, &mut errors);
+
+        assert!(errors.is_empty());
+        assert_eq!(parsed.mutability.as_deref(), Some("readOnly"));
+        assert!(parsed.caseExact);
+    }
+
+    #[test]
+    fn parseFieldAttrs_collects_a_bracketed_list() {
+        let (attrs, mut errors) =
+            fieldAttrs(r#"#[scim(canonical_values = ["active", "disabled"])]"#);
+        let parsed = parseFieldAttrs(&attrs, &mut errors);
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            parsed.canonicalValues,
+            vec![String::from("active"), String::from("disabled")]
+        );
+    }
+
+    #[test]
+    fn parseFieldAttrs_reports_an_unknown_key() {
+        let (attrs, mut errors) = fieldAttrs(r#"#[scim(bogus = "x")]"#);
+        parseFieldAttrs(&attrs, &mut errors);
+
+        assert_eq!(errors.len(), 1);
+    }
+}
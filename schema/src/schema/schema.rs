@@ -5,6 +5,14 @@ use std::fs::File;
 use std::io::Read;
 use std::rc::Rc;
 
+pub mod canonical;
+pub mod codegen;
+pub mod compat;
+pub mod fieldid;
+pub mod reflect;
+
+use self::fieldid::{FieldId, FieldsMap};
+
 const ATTR_DELIM: &str = ".";
 const validTypes: Vec<&str> = vec![
     "string",
@@ -94,19 +102,157 @@ pub struct Schema {
     #[serde(skip)]
     text: String,
     #[serde(skip)]
-    uniqueAts: Vec<String>,
+    fields: FieldsMap, // interned name -> FieldId map, built while loading
+    #[serde(skip)]
+    attrById: Vec<Rc<AttrType>>, // indexed by FieldId
+    #[serde(skip)]
+    uniqueAts: Vec<FieldId>,
     #[serde(skip)]
-    requiredAts: Vec<String>,
+    requiredAts: Vec<FieldId>,
     #[serde(skip)]
-    atsNeverRtn: Vec<String>, // names of attributes that are never returned
+    atsNeverRtn: Vec<FieldId>, // attributes that are never returned
     #[serde(skip)]
-    atsAlwaysRtn: Vec<String>, // names of attributes that are always returned
+    atsAlwaysRtn: Vec<FieldId>, // attributes that are always returned
     #[serde(skip)]
-    atsRequestRtn: Vec<String>, // names of attributes that are returned if requested
+    atsRequestRtn: Vec<FieldId>, // attributes that are returned if requested
     #[serde(skip)]
-    atsDefaultRtn: Vec<String>, // names of attributes that are returned by default
+    atsDefaultRtn: Vec<FieldId>, // attributes that are returned by default
     #[serde(skip)]
-    atsReadOnly: Vec<String>, // names of attributes that are readonly
+    atsReadOnly: Vec<FieldId>, // attributes that are readonly
+}
+
+impl Schema {
+    /// Looks up the attribute interned under `id`, if any.
+    pub fn attr_by_id(&self, id: FieldId) -> Option<&Rc<AttrType>> {
+        self.attrById.get(id.index())
+    }
+
+    /// Looks up the `FieldId` that `path` (e.g. `name.familyName`) was
+    /// interned under, if it was interned while loading this schema.
+    pub fn id_of(&self, path: &str) -> Option<FieldId> {
+        // paths are interned lowercased, see `validate`
+        self.fields.id_of(&path.to_ascii_lowercase())
+    }
+
+    /// Builds a `Schema` directly from its parts rather than by parsing a
+    /// schema JSON file, then runs `validate` on it to fill `attrMap`, the
+    /// interned `FieldId`s and the per-category lists; validation errors are
+    /// discarded. Used by `#[derive(ScimResource)]` to produce a
+    /// schema straight from a Rust struct definition.
+    pub fn from_parts(id: String, name: String, description: String, attributes: Vec<Rc<AttrType>>) -> Schema {
+        let mut sc = Schema {
+            id,
+            name,
+            description,
+            attributes,
+            meta: Meta {
+                location: String::new(),
+                resourceType: String::new(),
+            },
+            attrMap: HashMap::new(),
+            text: String::new(),
+            fields: FieldsMap::new(),
+            attrById: Vec::new(),
+            uniqueAts: Vec::new(),
+            requiredAts: Vec::new(),
+            atsNeverRtn: Vec::new(),
+            atsAlwaysRtn: Vec::new(),
+            atsRequestRtn: Vec::new(),
+            atsDefaultRtn: Vec::new(),
+            atsReadOnly: Vec::new(),
+        };
+        let _ = validate(&mut sc);
+        sc
+    }
+}
+
+/// Builds an `AttrType` from already-known values instead of by parsing a
+/// schema JSON file, deriving the same `is*` flags `setAttrDefaults` would.
+/// `AttrType`'s fields aren't `pub`, so this is also how
+/// `#[derive(ScimResource)]` — which expands in a downstream crate — has to
+/// build one.
+#[allow(clippy::too_many_arguments)]
+pub fn build_attr_type(
+    name: String,
+    scimType: String,
+    required: bool,
+    multiValued: bool,
+    mutability: String,
+    returned: String,
+    uniqueness: String,
+    caseExact: bool,
+    canonicalValues: Vec<String>,
+    referenceTypes: Vec<String>,
+    subAttributes: Vec<Rc<AttrType>>,
+) -> AttrType {
+    let mut a = new_attr_type();
+    a.normName = name.to_ascii_lowercase();
+    a.name = name;
+    a.Type = scimType;
+    a.required = required;
+    a.multiValued = multiValued;
+    a.mutability = mutability;
+    a.returned = returned;
+    a.uniqueness = uniqueness;
+    a.caseExact = caseExact;
+    a.canonicalValues = canonicalValues;
+    a.referenceTypes = referenceTypes;
+    a.subAttributes = subAttributes;
+    a.isUnique = (a.uniqueness == "server") || (a.uniqueness == "global");
+    a.isComplex = a.Type == "complex";
+    a.isRef = a.Type == "reference";
+    a.isSimple = !a.isComplex && !a.isRef;
+    a.isStringType = a.Type == "string" || a.isRef;
+    a.isReadOnly = a.mutability == "readonly";
+    a.isImmutable = a.mutability == "immutable";
+    a
+}
+
+/// Builds an already-valid `AttrType` for tests, bypassing `load_schema`'s
+/// JSON parsing and `validate`'s (separately broken) bookkeeping.
+#[cfg(test)]
+pub(crate) fn test_attr(name: &str, scimType: &str) -> AttrType {
+    let mut a = new_attr_type();
+    a.name = String::from(name);
+    a.normName = name.to_ascii_lowercase();
+    a.Type = String::from(scimType);
+    a.isComplex = scimType == "complex";
+    a.isRef = scimType == "reference";
+    a.isSimple = !a.isComplex && !a.isRef;
+    a.isStringType = scimType == "string" || a.isRef;
+    a
+}
+
+/// Builds a minimal, already-valid `Schema` for tests, with `attrMap`
+/// populated from `attributes`' already-set `normName`s.
+#[cfg(test)]
+pub(crate) fn test_schema(attributes: Vec<Rc<AttrType>>) -> Schema {
+    let mut attrMap = HashMap::new();
+    for a in attributes.iter() {
+        attrMap.insert(a.normName.clone(), Rc::clone(a));
+    }
+
+    Schema {
+        id: String::from("urn:test:schema:Test"),
+        name: String::from("Test"),
+        description: String::new(),
+        attributes,
+        meta: Meta {
+            location: String::new(),
+            resourceType: String::new(),
+        },
+        attrMap,
+        text: String::new(),
+        fields: FieldsMap::new(),
+        attrById: Vec::new(),
+        uniqueAts: Vec::new(),
+        requiredAts: Vec::new(),
+        atsNeverRtn: Vec::new(),
+        atsAlwaysRtn: Vec::new(),
+        atsRequestRtn: Vec::new(),
+        atsDefaultRtn: Vec::new(),
+        atsReadOnly: Vec::new(),
+    }
 }
 
 pub struct SchemaError {
@@ -129,6 +275,14 @@ impl From<std::io::Error> for SchemaError {
     }
 }
 
+impl From<fieldid::FieldIdOverflow> for SchemaError {
+    fn from(_: fieldid::FieldIdOverflow) -> SchemaError {
+        SchemaError {
+            details: String::from("schema has more attributes than fit in a FieldId"),
+        }
+    }
+}
+
 /// see section https://tools.ietf.org/html/rfc7643#section-2.2 for the defaults
 pub fn new_attr_type() -> AttrType {
     AttrType {
@@ -221,7 +375,7 @@ fn setAttrDefaults(attr: &mut AttrType) {
     }
 }
 
-fn validate(sc: &mut Schema) -> Vec<&str> {
+fn validate(sc: &mut Schema) -> Result<Vec<&str>, SchemaError> {
     let mut ve = Vec::new();
 
     if sc.id == "" {
@@ -230,32 +384,36 @@ fn validate(sc: &mut Schema) -> Vec<&str> {
 
     if sc.attributes.len() == 0 {
         ve.push("A schema should contain atleast one attribute");
-        return ve;
+        return Ok(ve);
     }
 
     let validNameRegex: Regex = Regex::new(r"^[0-9A-Za-z_$-]+$").unwrap();
     for attr in sc.attributes.iter_mut() {
-        sc.validateAttrType(attr, &mut ve, &validNameRegex);
+        validateAttrType(sc, attr, &mut ve, &validNameRegex)?;
         let name = attr.name.to_ascii_lowercase();
         sc.attrMap.insert(name.clone(), Rc::clone(attr));
+
+        let id = sc.fields.intern(&name)?;
+        sc.attrById.push(Rc::clone(attr));
+
         if attr.isUnique {
-            sc.uniqueAts.push(name.clone())
+            sc.uniqueAts.push(id)
         }
 
         if attr.required {
-            sc.requiredAts.push(name.clone())
+            sc.requiredAts.push(id)
         }
     }
 
-    return ve;
+    Ok(ve)
 }
 
 fn validateAttrType(
-    sc: &Schema,
+    sc: &mut Schema,
     attr: Rc<AttrType>,
     ve: &mut Vec<&str>,
     validNameRegex: &Regex,
-) -> (Vec<String>, Vec<String>) {
+) -> Result<(), SchemaError> {
     // ATTRNAME   = ALPHA *(nameChar)
     // nameChar   = "$" / "-" / "_" / DIGIT / ALPHA
     // ALPHA      =  %x41-5A / %x61-7A   ; A-Z / a-z
@@ -314,23 +472,26 @@ fn validateAttrType(
     attr.schemaId = sc.id.clone();
     attr.normName = attr.name.to_ascii_lowercase();
 
-    let mut uniqueAts: Vec<String> = vec![];
-    let mut requiredAts: Vec<String> = vec![];
-
     if attr.isComplex {
         //log.Debugf("validating sub-attributes of attributetype %s\n", attr.Name)
         for sa in attr.subAttributes {
             //log.Tracef("validating sub-type %s of %s", sa.Name, attr.Name);
             let mut a = &sa;
-            validateAttrType(sc, sa, ve, validNameRegex);
+            validateAttrType(sc, sa, ve, validNameRegex)?;
             a.parent = Some(Rc::clone(attr));
             attr.subAttrMap.insert(sa.normName.clone(), Rc::clone(a));
-            let name = format!("{}{}{}", &attr.normName, ATTR_DELIM, &sa.normName);
+
+            // intern the dotted path too, e.g. "name.familyname", so
+            // Schema::id_of resolves sub-attributes the same as top-level ones
+            let path = format!("{}{}{}", &attr.normName, ATTR_DELIM, &sa.normName);
+            let id = sc.fields.intern(&path)?;
+            sc.attrById.push(Rc::clone(a));
+
             if sa.isUnique {
-                uniqueAts.push(name.clone());
+                sc.uniqueAts.push(id);
             }
             if sa.required {
-                requiredAts.push(name)
+                sc.requiredAts.push(id);
             }
         }
 
@@ -340,6 +501,8 @@ fn validateAttrType(
             setAttrDefaults(attr);
         }
     }
+
+    Ok(())
 }
 
 fn exists(val: &String, list: Vec<&str>) -> bool {
@@ -0,0 +1,176 @@
+//! Normalizes a `Schema` into a stable canonical form and fingerprints it
+//! with a 64-bit Rabin fingerprint, for compiled-schema caching.
+
+use super::{AttrType, Schema};
+use serde_json::{Map, Value};
+use std::rc::Rc;
+
+/// Starting value (and XOR mask) for the Rabin fingerprint, taken from the
+/// Avro spec's reference 64-bit fingerprinting algorithm.
+const EMPTY: u64 = 0xc15d213aa4d7a795;
+
+fn attrToValue(attr: &AttrType) -> Value {
+    let mut map = Map::new();
+
+    map.insert("name".to_string(), Value::String(attr.normName.clone()));
+    map.insert("type".to_string(), Value::String(attr.Type.to_ascii_lowercase()));
+
+    // RFC 7643 section 2.2 defaults are omitted so that a schema written
+    // with or without them canonicalizes identically
+    if attr.caseExact {
+        map.insert("caseExact".to_string(), Value::Bool(true));
+    }
+    if attr.multiValued {
+        map.insert("multiValued".to_string(), Value::Bool(true));
+    }
+    if attr.required {
+        map.insert("required".to_string(), Value::Bool(true));
+    }
+
+    let mutability = attr.mutability.to_ascii_lowercase();
+    if mutability != "readwrite" {
+        map.insert("mutability".to_string(), Value::String(mutability));
+    }
+
+    let returned = attr.returned.to_ascii_lowercase();
+    if returned != "default" {
+        map.insert("returned".to_string(), Value::String(returned));
+    }
+
+    let uniqueness = attr.uniqueness.to_ascii_lowercase();
+    if uniqueness != "none" {
+        map.insert("uniqueness".to_string(), Value::String(uniqueness));
+    }
+
+    if !attr.referenceTypes.is_empty() {
+        let mut refTypes = attr.referenceTypes.clone();
+        refTypes.sort();
+        map.insert(
+            "referenceTypes".to_string(),
+            Value::Array(refTypes.into_iter().map(Value::String).collect()),
+        );
+    }
+
+    if !attr.canonicalValues.is_empty() {
+        let mut values = attr.canonicalValues.clone();
+        values.sort();
+        map.insert(
+            "canonicalValues".to_string(),
+            Value::Array(values.into_iter().map(Value::String).collect()),
+        );
+    }
+
+    if !attr.subAttributes.is_empty() {
+        map.insert(
+            "subAttributes".to_string(),
+            Value::Array(sortedAttrValues(&attr.subAttributes)),
+        );
+    }
+
+    Value::Object(map)
+}
+
+/// Sorts `attrs` by `normName` so that attribute ordering never affects the
+/// canonical form, then renders each to its `Value`.
+fn sortedAttrValues(attrs: &Vec<Rc<AttrType>>) -> Vec<Value> {
+    let mut sorted: Vec<&Rc<AttrType>> = attrs.iter().collect();
+    sorted.sort_by(|a, b| a.normName.cmp(&b.normName));
+    sorted.into_iter().map(|a| attrToValue(a)).collect()
+}
+
+/// Produces a normalized JSON representation of `schema`: RFC 7643 defaults
+/// are dropped, enum-valued fields are lowercased, `attributes` and every
+/// `subAttributes` list are sorted by `normName`, and the result is rendered
+/// without whitespace. Two schemas that differ only in those ways canonicalize
+/// to the same string.
+pub fn canonical_form(schema: &Schema) -> String {
+    let mut map = Map::new();
+    map.insert("id".to_string(), Value::String(schema.id.clone()));
+    map.insert("name".to_string(), Value::String(schema.name.clone()));
+    if !schema.description.is_empty() {
+        map.insert("description".to_string(), Value::String(schema.description.clone()));
+    }
+    map.insert(
+        "attributes".to_string(),
+        Value::Array(sortedAttrValues(&schema.attributes)),
+    );
+
+    // serde_json's compact writer never emits insignificant whitespace
+    serde_json::to_string(&Value::Object(map)).unwrap_or_default()
+}
+
+/// Precomputes the 256-entry Rabin fingerprint table, per the Avro spec's
+/// reference algorithm: each entry is 8 rounds of `fp = (fp >> 1) ^ (EMPTY &
+/// -(fp & 1))` starting from the table index.
+fn fingerprintTable() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    for i in 0..256u64 {
+        let mut fp = i;
+        for _ in 0..8 {
+            fp = (fp >> 1) ^ (EMPTY & 0u64.wrapping_sub(fp & 1));
+        }
+        table[i as usize] = fp;
+    }
+    table
+}
+
+fn fingerprintBytes(bytes: &[u8]) -> u64 {
+    let table = fingerprintTable();
+    let mut fp = EMPTY;
+    for &byte in bytes {
+        fp = (fp >> 8) ^ table[((fp ^ byte as u64) & 0xff) as usize];
+    }
+    fp
+}
+
+/// Computes a 64-bit Rabin fingerprint over the UTF-8 bytes of `schema`'s
+/// `canonical_form`, suitable for cheaply caching compiled schemas and
+/// detecting when a schema has genuinely changed.
+pub fn fingerprint(schema: &Schema) -> u64 {
+    fingerprintBytes(canonical_form(schema).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{test_attr, test_schema};
+
+    #[test]
+    fn fingerprint_of_no_bytes_is_the_starting_value() {
+        assert_eq!(fingerprintBytes(&[]), EMPTY);
+    }
+
+    #[test]
+    fn canonical_form_drops_rfc7643_defaults() {
+        let schema = test_schema(vec![Rc::new(test_attr("userName", "string"))]);
+        let form = canonical_form(&schema);
+
+        assert!(!form.contains("mutability"));
+        assert!(!form.contains("returned"));
+        assert!(!form.contains("uniqueness"));
+        assert!(!form.contains("required"));
+    }
+
+    #[test]
+    fn attribute_order_does_not_affect_canonical_form_or_fingerprint() {
+        let a = test_schema(vec![
+            Rc::new(test_attr("userName", "string")),
+            Rc::new(test_attr("active", "boolean")),
+        ]);
+        let b = test_schema(vec![
+            Rc::new(test_attr("active", "boolean")),
+            Rc::new(test_attr("userName", "string")),
+        ]);
+
+        assert_eq!(canonical_form(&a), canonical_form(&b));
+        assert_eq!(fingerprint(&a), fingerprint(&b));
+    }
+
+    #[test]
+    fn fingerprint_changes_when_a_type_changes() {
+        let a = test_schema(vec![Rc::new(test_attr("score", "integer"))]);
+        let b = test_schema(vec![Rc::new(test_attr("score", "string"))]);
+
+        assert_ne!(fingerprint(&a), fingerprint(&b));
+    }
+}
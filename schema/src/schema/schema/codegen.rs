@@ -0,0 +1,196 @@
+//! Generates strongly-typed Rust resource structs from a parsed `Schema`.
+//! The result is a plain `String` of Rust source, so it can be returned to a
+//! caller directly or written to `OUT_DIR` from a `build.rs`.
+
+use super::{AttrType, Schema};
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Maps a SCIM `Type` to the Rust type used for a generated field.
+/// `complex` is handled separately since it needs the generated struct's
+/// name, not a fixed Rust type.
+fn scalar_rust_type(scimType: &str) -> &'static str {
+    match scimType {
+        "string" | "reference" => "String",
+        "boolean" => "bool",
+        "integer" => "i64",
+        "decimal" => "f64",
+        "datetime" => {
+            if cfg!(feature = "chrono-datetime") {
+                "chrono::DateTime<chrono::Utc>"
+            } else {
+                "String"
+            }
+        }
+        "binary" => "Vec<u8>",
+        // unknown types fall back to an untyped JSON value rather than
+        // failing codegen outright
+        _ => "serde_json::Value",
+    }
+}
+
+/// Turns an attribute name such as `displayName` or `$ref` into a valid,
+/// idiomatic `snake_case` Rust field identifier.
+fn fieldIdent(name: &str) -> String {
+    let mut ident = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && !ident.is_empty() {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+        } else if c == '_' || c == '-' {
+            ident.push('_');
+        }
+        // other characters (e.g. '$') are dropped from the identifier but
+        // preserved via #[serde(rename = "...")]
+    }
+
+    if ident.is_empty() || ident.chars().next().unwrap().is_ascii_digit() {
+        ident = format!("attr_{}", ident);
+    }
+
+    // avoid clashing with Rust keywords such as `type` or `ref`
+    match ident.as_str() {
+        "type" | "ref" | "move" | "use" | "match" | "final" => {
+            ident.push('_');
+            ident
+        }
+        _ => ident,
+    }
+}
+
+/// Turns a schema or attribute name into a `PascalCase` struct name.
+fn structIdent(name: &str) -> String {
+    let mut ident = String::new();
+    let mut capitalizeNext = true;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if capitalizeNext {
+                ident.push(c.to_ascii_uppercase());
+                capitalizeNext = false;
+            } else {
+                ident.push(c);
+            }
+        } else {
+            capitalizeNext = true;
+        }
+    }
+
+    if ident.is_empty() {
+        ident = String::from("Resource");
+    }
+
+    ident
+}
+
+/// Emits one struct for `subAttributes`, plus one per nested `complex`
+/// sub-attribute. `structName` is the `PascalCase` name of the struct
+/// being emitted.
+fn emitComplexStruct(structName: &str, subAttributes: &Vec<Rc<AttrType>>, out: &mut String) {
+    // emit nested complex types first so the outer struct can reference them
+    for sub in subAttributes.iter() {
+        if sub.Type == "complex" {
+            let nestedName = format!("{}{}", structName, structIdent(&sub.name));
+            emitComplexStruct(&nestedName, &sub.subAttributes, out);
+        }
+    }
+
+    out.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+    out.push_str(&format!("pub struct {} {{\n", structName));
+    for sub in subAttributes.iter() {
+        emitField(structName, sub, out);
+    }
+    out.push_str("}\n\n");
+}
+
+fn rustTypeOf(structName: &str, attr: &AttrType) -> String {
+    let inner = if attr.Type == "complex" {
+        format!("{}{}", structName, structIdent(&attr.name))
+    } else {
+        scalar_rust_type(&attr.Type).to_string()
+    };
+
+    let inner = if attr.multiValued {
+        format!("Vec<{}>", inner)
+    } else {
+        inner
+    };
+
+    if attr.required {
+        inner
+    } else {
+        format!("Option<{}>", inner)
+    }
+}
+
+fn emitField(structName: &str, attr: &AttrType, out: &mut String) {
+    out.push_str(&format!("    #[serde(rename = \"{}\")]\n", attr.name));
+    out.push_str(&format!(
+        "    pub {}: {},\n",
+        fieldIdent(&attr.name),
+        rustTypeOf(structName, attr)
+    ));
+}
+
+/// Generates Rust source for the top-level resource struct described by
+/// `schema`, plus one struct per `complex` attribute.
+pub fn generate(schema: &Schema) -> String {
+    let structName = structIdent(&schema.name);
+
+    let mut out = String::new();
+    out.push_str("// @generated by phoenix's schema::codegen, do not edit by hand.\n");
+    out.push_str("use serde::{Deserialize, Serialize};\n\n");
+
+    emitComplexStruct(&structName, &schema.attributes, &mut out);
+
+    out
+}
+
+/// `build.rs`-friendly entry point: generates Rust source for every schema in
+/// `schemas` and writes it to `dest`, typically `$OUT_DIR/scim_types.rs`, for
+/// callers to vendor with `include!(concat!(env!("OUT_DIR"), "/scim_types.rs"))`.
+pub fn generate_to_file<P: AsRef<Path>>(schemas: &[&Schema], dest: P) -> io::Result<()> {
+    let mut out = String::new();
+    out.push_str("// @generated by phoenix's schema::codegen, do not edit by hand.\n");
+    out.push_str("use serde::{Deserialize, Serialize};\n\n");
+
+    for schema in schemas {
+        let structName = structIdent(&schema.name);
+        emitComplexStruct(&structName, &schema.attributes, &mut out);
+    }
+
+    std::fs::write(dest, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{test_attr, test_schema};
+
+    #[test]
+    fn fieldIdent_handles_dollar_ref_and_keywords() {
+        assert_eq!(fieldIdent("$ref"), "ref_");
+        assert_eq!(fieldIdent("displayName"), "display_name");
+        assert_eq!(fieldIdent("type"), "type_");
+    }
+
+    #[test]
+    fn structIdent_pascal_cases_schema_names() {
+        assert_eq!(structIdent("enterprise-user"), "EnterpriseUser");
+        assert_eq!(structIdent("User"), "User");
+    }
+
+    #[test]
+    fn generate_renames_fields_to_their_wire_name() {
+        let displayName = test_attr("displayName", "string");
+        let schema = test_schema(vec![Rc::new(displayName)]);
+
+        let out = generate(&schema);
+
+        assert!(out.contains("pub struct Test"));
+        assert!(out.contains("#[serde(rename = \"displayName\")]"));
+        assert!(out.contains("pub display_name: Option<String>"));
+    }
+}
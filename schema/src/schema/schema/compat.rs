@@ -0,0 +1,210 @@
+//! Checks whether a new version of a `Schema` is safe to deploy over an
+//! existing one: `old` is the schema existing resources were written
+//! against, `new` is the one being uploaded.
+
+use super::{AttrType, Schema, ATTR_DELIM};
+use std::rc::Rc;
+
+/// Severity of a detected schema change.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// `new` may reject or misinterpret data that was valid under `old`.
+    Breaking,
+    /// The change is safe but worth a human's attention.
+    Warning,
+}
+
+/// A single detected difference between two schema versions.
+#[derive(Debug)]
+pub struct Incompatibility {
+    /// Fully-qualified attribute path built from lowercased attribute names,
+    /// e.g. `name.familyname`.
+    pub path: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+fn breaking(path: &str, message: String) -> Incompatibility {
+    Incompatibility {
+        path: String::from(path),
+        severity: Severity::Breaking,
+        message,
+    }
+}
+
+fn warning(path: &str, message: String) -> Incompatibility {
+    Incompatibility {
+        path: String::from(path),
+        severity: Severity::Warning,
+        message,
+    }
+}
+
+/// A type change is safe only when every value of `oldType` is still a valid
+/// value of `newType`.
+fn typesCompatible(oldType: &str, newType: &str) -> bool {
+    oldType == newType || (oldType == "integer" && newType == "decimal")
+}
+
+/// `true` when `newMutability` accepts strictly fewer writes than
+/// `oldMutability` did.
+fn tightensMutability(oldMutability: &str, newMutability: &str) -> bool {
+    let writable = |m: &str| m == "readwrite" || m == "writeonly";
+    writable(oldMutability) && !writable(newMutability)
+}
+
+/// `true` when `newUniqueness` is stricter than `oldUniqueness`, i.e. data
+/// that didn't have to be unique before now must be.
+fn tightensUniqueness(oldUniqueness: &str, newUniqueness: &str) -> bool {
+    oldUniqueness == "none" && (newUniqueness == "server" || newUniqueness == "global")
+}
+
+/// Recursively compares `old` and `new`'s own characteristics (not their
+/// sub-attributes, which `compareSubAttributes` handles) and appends any
+/// incompatibilities found to `out`.
+fn compareAttrType(path: &str, old: &Rc<AttrType>, new: &Rc<AttrType>, out: &mut Vec<Incompatibility>) {
+    if !old.required && new.required {
+        out.push(breaking(
+            path,
+            String::from("became required; existing resources may lack it"),
+        ));
+    }
+
+    if old.Type != new.Type {
+        if typesCompatible(&old.Type, &new.Type) {
+            out.push(warning(
+                path,
+                format!("type widened from '{}' to '{}'", old.Type, new.Type),
+            ));
+        } else {
+            out.push(breaking(
+                path,
+                format!("type changed from '{}' to '{}'", old.Type, new.Type),
+            ));
+        }
+    }
+
+    if old.multiValued != new.multiValued {
+        out.push(breaking(path, String::from("multiValued changed")));
+    }
+
+    if tightensMutability(&old.mutability, &new.mutability) {
+        out.push(breaking(
+            path,
+            format!(
+                "mutability tightened from '{}' to '{}'",
+                old.mutability, new.mutability
+            ),
+        ));
+    }
+
+    if tightensUniqueness(&old.uniqueness, &new.uniqueness) {
+        out.push(breaking(
+            path,
+            format!(
+                "uniqueness tightened from '{}' to '{}'",
+                old.uniqueness, new.uniqueness
+            ),
+        ));
+    }
+
+    compareSubAttributes(path, old, new, out);
+}
+
+fn compareSubAttributes(path: &str, old: &Rc<AttrType>, new: &Rc<AttrType>, out: &mut Vec<Incompatibility>) {
+    for (subName, oldSub) in old.subAttrMap.iter() {
+        let subPath = format!("{}{}{}", path, ATTR_DELIM, subName);
+        match new.subAttrMap.get(subName) {
+            None => out.push(breaking(&subPath, String::from("sub-attribute was removed"))),
+            Some(newSub) => compareAttrType(&subPath, oldSub, newSub, out),
+        }
+    }
+
+    for (subName, newSub) in new.subAttrMap.iter() {
+        if !old.subAttrMap.contains_key(subName) && newSub.required {
+            let subPath = format!("{}{}{}", path, ATTR_DELIM, subName);
+            out.push(breaking(
+                &subPath,
+                String::from("new required sub-attribute; existing resources may lack it"),
+            ));
+        }
+    }
+}
+
+/// Compares `old` against `new` and reports every incompatibility found.
+/// An empty result means `new` is a safe superset of `old`.
+pub fn check_compatibility(old: &Schema, new: &Schema) -> Vec<Incompatibility> {
+    let mut out = Vec::new();
+
+    for (name, oldAttr) in old.attrMap.iter() {
+        match new.attrMap.get(name) {
+            None => out.push(breaking(&oldAttr.normName, format!("attribute '{}' was removed", name))),
+            Some(newAttr) => compareAttrType(&oldAttr.normName, oldAttr, newAttr, &mut out),
+        }
+    }
+
+    for (name, newAttr) in new.attrMap.iter() {
+        if !old.attrMap.contains_key(name) && newAttr.required {
+            out.push(breaking(
+                &newAttr.normName,
+                String::from("new required attribute; existing resources may lack it"),
+            ));
+        }
+    }
+
+    // `attrMap` is a HashMap, so iteration order (and thus `out`'s order) is
+    // nondeterministic run-to-run; sort so callers can diff two reports
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{test_attr, test_schema};
+
+    #[test]
+    fn flags_a_removed_attribute_as_breaking() {
+        let old = test_schema(vec![Rc::new(test_attr("userName", "string"))]);
+        let new = test_schema(vec![]);
+
+        let out = check_compatibility(&old, &new);
+
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].severity, Severity::Breaking);
+    }
+
+    #[test]
+    fn flags_required_flipping_on_as_breaking() {
+        let oldAttr = test_attr("userName", "string");
+        let mut newAttr = test_attr("userName", "string");
+        newAttr.required = true;
+        let old = test_schema(vec![Rc::new(oldAttr)]);
+        let new = test_schema(vec![Rc::new(newAttr)]);
+
+        let out = check_compatibility(&old, &new);
+
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].severity, Severity::Breaking);
+    }
+
+    #[test]
+    fn allows_a_safe_integer_to_decimal_widening() {
+        let old = test_schema(vec![Rc::new(test_attr("score", "integer"))]);
+        let new = test_schema(vec![Rc::new(test_attr("score", "decimal"))]);
+
+        let out = check_compatibility(&old, &new);
+
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn safe_superset_is_empty() {
+        let attr = test_attr("userName", "string");
+        let old = test_schema(vec![Rc::new(attr)]);
+        let new = test_schema(vec![Rc::new(test_attr("userName", "string"))]);
+
+        assert!(check_compatibility(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+//! Interns attribute paths into compact `FieldId`s so hot paths can key on
+//! a cheap `u16` instead of hashing a `String` on every lookup.
+
+use std::collections::HashMap;
+
+/// Compact identifier for an interned attribute path, e.g. `name.familyname`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FieldId(u16);
+
+impl FieldId {
+    /// Index of this id's attribute in the order it was interned.
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Returned by `FieldsMap::intern` when a schema has more distinct attribute
+/// paths than fit in a `u16`.
+#[derive(Debug)]
+pub struct FieldIdOverflow;
+
+/// Bidirectional `name <-> FieldId` map, built once at schema load time and
+/// then used read-only by query and projection code.
+#[derive(Debug, Default)]
+pub struct FieldsMap {
+    byName: HashMap<String, FieldId>,
+    byId: Vec<String>,
+}
+
+impl FieldsMap {
+    pub fn new() -> FieldsMap {
+        FieldsMap {
+            byName: HashMap::new(),
+            byId: Vec::new(),
+        }
+    }
+
+    fn next(&self) -> Result<FieldId, FieldIdOverflow> {
+        let id: u16 = self
+            .byId
+            .len()
+            .try_into()
+            .ok()
+            .and_then(|id: u16| id.checked_add(0))
+            .ok_or(FieldIdOverflow)?;
+        Ok(FieldId(id))
+    }
+
+    /// Interns `path`, returning its existing `FieldId` if it was already
+    /// interned, or assigning and returning the next one.
+    pub fn intern(&mut self, path: &str) -> Result<FieldId, FieldIdOverflow> {
+        if let Some(id) = self.byName.get(path) {
+            return Ok(*id);
+        }
+
+        let id = self.next()?;
+        self.byName.insert(path.to_string(), id);
+        self.byId.push(path.to_string());
+        Ok(id)
+    }
+
+    /// Looks up the `FieldId` already interned for `path`, if any.
+    pub fn id_of(&self, path: &str) -> Option<FieldId> {
+        self.byName.get(path).copied()
+    }
+
+    /// Looks up the attribute path that `id` was interned from.
+    pub fn name_of(&self, id: FieldId) -> Option<&str> {
+        self.byId.get(id.0 as usize).map(|s| s.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_the_same_path_twice_returns_the_same_id() {
+        let mut fields = FieldsMap::new();
+        let first = fields.intern("name.familyname").unwrap();
+        let second = fields.intern("name.familyname").unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn ids_are_assigned_sequentially_and_round_trip_through_name_of() {
+        let mut fields = FieldsMap::new();
+        let userName = fields.intern("username").unwrap();
+        let active = fields.intern("active").unwrap();
+
+        assert_eq!(userName.index(), 0);
+        assert_eq!(active.index(), 1);
+        assert_eq!(fields.name_of(userName), Some("username"));
+        assert_eq!(fields.id_of("active"), Some(active));
+    }
+
+    #[test]
+    fn id_of_is_none_for_a_path_that_was_never_interned() {
+        let fields = FieldsMap::new();
+        assert_eq!(fields.id_of("name.familyname"), None);
+    }
+}
@@ -0,0 +1,340 @@
+//! Runtime reflection over a resource value by SCIM attribute path: given a
+//! `Schema`, callers get and set values on a resource without hand-coded
+//! match arms per attribute.
+
+use super::AttrType;
+use super::Schema;
+use serde_json::{Map, Value};
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub enum ReflectError {
+    UnknownAttribute(String),
+    ReadOnly(String),
+    TypeMismatch { path: String, expected: String },
+    InvalidCanonicalValue { path: String, value: String },
+}
+
+/// The SCIM value filter on one `.`-delimited path segment that narrows a
+/// multi-valued complex attribute, e.g. the `[type eq "work"]` in
+/// `emails[type eq "work"].value`. Only the `eq` operator is supported.
+struct Filter {
+    attr: String,
+    value: String,
+}
+
+/// Splits a path on `.`, ignoring any `.` that appears inside a `[...]`
+/// value filter.
+fn splitPath(path: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0usize;
+    for (i, c) in path.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '.' if depth == 0 => {
+                parts.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&path[start..]);
+    parts
+}
+
+/// Splits a raw segment such as `emails[type eq "work"]` into its attribute
+/// name and an optional `eq` filter.
+fn parseSegment(raw: &str) -> (String, Option<Filter>) {
+    match raw.find('[') {
+        None => (raw.to_string(), None),
+        Some(open) => {
+            let name = raw[..open].to_string();
+            let close = raw.rfind(']').unwrap_or(raw.len());
+            if close <= open {
+                // malformed brackets, e.g. a stray `]` before the `[`; treat
+                // the whole segment as an (unresolvable) attribute name
+                // rather than panicking on the out-of-order slice
+                return (raw.to_string(), None);
+            }
+            let expr = raw[open + 1..close].trim();
+            let mut tokens = expr.splitn(3, ' ');
+            let filterAttr = tokens.next().unwrap_or("").to_string();
+            let _op = tokens.next(); // only `eq` is supported
+            let filterValue = tokens
+                .next()
+                .unwrap_or("")
+                .trim()
+                .trim_matches('"')
+                .to_string();
+            (
+                name,
+                Some(Filter {
+                    attr: filterAttr,
+                    value: filterValue,
+                }),
+            )
+        }
+    }
+}
+
+/// Implemented by types that carry a `#[derive(ScimResource)]`-generated
+/// schema definition alongside their serde model, so the runtime `Schema`
+/// and the Rust struct stay defined from the same source.
+pub trait ScimResource {
+    /// The `AttrType` tree the derive macro generated from this struct's
+    /// fields, used both as `scim_schema()`'s top-level attributes and, for
+    /// nested `#[derive(ScimResource)]` structs, as a parent's
+    /// `subAttributes`.
+    fn __scim_sub_attributes() -> Vec<Rc<AttrType>>;
+
+    /// Builds the runtime `Schema` for this type.
+    fn scim_schema() -> Schema;
+}
+
+/// Resolves attribute paths against a `Schema`.
+pub struct SchemaRef;
+
+impl SchemaRef {
+    /// Walks `attrMap`/`subAttrMap` across `path`'s `.`-delimited segments
+    /// and returns a handle to the target attribute, or `None` if any
+    /// segment doesn't name a known attribute, or if a non-final segment
+    /// names a `multiValued` attribute without a `[attr eq "value"]` filter
+    /// to pick a single entry to descend into (e.g. `emails.value` instead
+    /// of `emails[type eq "work"].value`) — continuing such a path would
+    /// have no single entry to read or write.
+    pub fn resolve(schema: &Schema, path: &str) -> Option<AttrHandle> {
+        let mut chain: Vec<(Rc<AttrType>, Option<Filter>)> = Vec::new();
+        let mut parent: Option<Rc<AttrType>> = None;
+
+        let segments: Vec<&str> = splitPath(path);
+        let lastIdx = segments.len().checked_sub(1)?;
+        for (i, raw) in segments.into_iter().enumerate() {
+            let (name, filter) = parseSegment(raw);
+            let normName = name.to_ascii_lowercase();
+            let attr = match &parent {
+                None => Rc::clone(schema.attrMap.get(&normName)?),
+                Some(p) => Rc::clone(p.subAttrMap.get(&normName)?),
+            };
+
+            if i != lastIdx && attr.multiValued && filter.is_none() {
+                return None;
+            }
+
+            parent = Some(Rc::clone(&attr));
+            chain.push((attr, filter));
+        }
+
+        let attr = parent?;
+        Some(AttrHandle { attr, chain })
+    }
+}
+
+/// A resolved pointer to one attribute, bound to the path it was resolved
+/// from. Borrow a `&Value`/`&mut Value` resource to read or write it.
+pub struct AttrHandle {
+    attr: Rc<AttrType>,
+    chain: Vec<(Rc<AttrType>, Option<Filter>)>,
+}
+
+impl AttrHandle {
+    /// Reads the value this handle points to out of `resource`, applying
+    /// any `[attr eq "value"]` filters along the way.
+    pub fn get<'v>(&self, resource: &'v Value) -> Option<&'v Value> {
+        let mut cur = resource;
+        for (attr, filter) in &self.chain {
+            cur = cur.get(&attr.name)?;
+            if let Some(filter) = filter {
+                cur = cur
+                    .as_array()?
+                    .iter()
+                    .find(|entry| entry.get(&filter.attr).and_then(Value::as_str) == Some(filter.value.as_str()))?;
+            }
+        }
+        Some(cur)
+    }
+
+    /// Writes `value` into `resource` at the path this handle points to,
+    /// creating intermediate objects and filtered array entries as needed.
+    /// Rejects writes to `readonly`/`immutable` attributes and values that
+    /// don't match the attribute's `Type`, `multiValued` or
+    /// `canonicalValues`.
+    pub fn set(&self, resource: &mut Value, value: Value) -> Result<(), ReflectError> {
+        if self.attr.isReadOnly || self.attr.isImmutable {
+            return Err(ReflectError::ReadOnly(self.attr.name.clone()));
+        }
+        self.validate(&value)?;
+
+        let mut cur = resource;
+        let lastIdx = self.chain.len() - 1;
+        for (i, (attr, filter)) in self.chain.iter().enumerate() {
+            if !cur.is_object() {
+                *cur = Value::Object(Map::new());
+            }
+            let entry = cur
+                .as_object_mut()
+                .unwrap()
+                .entry(attr.name.clone())
+                .or_insert(Value::Null);
+
+            cur = match filter {
+                None => entry,
+                Some(filter) => {
+                    if !entry.is_array() {
+                        *entry = Value::Array(Vec::new());
+                    }
+                    let arr = entry.as_array_mut().unwrap();
+                    let pos = arr
+                        .iter()
+                        .position(|e| e.get(&filter.attr).and_then(Value::as_str) == Some(filter.value.as_str()));
+                    let pos = pos.unwrap_or_else(|| {
+                        let mut entry = Map::new();
+                        entry.insert(filter.attr.clone(), Value::String(filter.value.clone()));
+                        arr.push(Value::Object(entry));
+                        arr.len() - 1
+                    });
+                    &mut arr[pos]
+                }
+            };
+
+            if i == lastIdx {
+                *cur = value;
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self, value: &Value) -> Result<(), ReflectError> {
+        if self.attr.multiValued {
+            let arr = value.as_array().ok_or_else(|| ReflectError::TypeMismatch {
+                path: self.attr.name.clone(),
+                expected: String::from("array"),
+            })?;
+            for v in arr {
+                self.validateOne(v)?;
+            }
+            Ok(())
+        } else {
+            self.validateOne(value)
+        }
+    }
+
+    fn validateOne(&self, value: &Value) -> Result<(), ReflectError> {
+        if !self.attr.canonicalValues.is_empty() {
+            if let Some(s) = value.as_str() {
+                if !self.attr.canonicalValues.iter().any(|c| c == s) {
+                    return Err(ReflectError::InvalidCanonicalValue {
+                        path: self.attr.name.clone(),
+                        value: s.to_string(),
+                    });
+                }
+            }
+        }
+
+        let matches = match self.attr.Type.as_str() {
+            "string" | "reference" | "datetime" | "binary" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "decimal" => value.is_number(),
+            "complex" => value.is_object(),
+            _ => true,
+        };
+
+        if !matches {
+            return Err(ReflectError::TypeMismatch {
+                path: self.attr.name.clone(),
+                expected: self.attr.Type.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{test_attr, test_schema};
+
+    fn emailsSchema(multiValued: bool) -> Schema {
+        let value = Rc::new(test_attr("value", "string"));
+        let typ = Rc::new(test_attr("type", "string"));
+
+        let mut emails = test_attr("emails", "complex");
+        emails.multiValued = multiValued;
+        emails.subAttrMap.insert(String::from("value"), Rc::clone(&value));
+        emails.subAttrMap.insert(String::from("type"), Rc::clone(&typ));
+
+        test_schema(vec![Rc::new(emails)])
+    }
+
+    #[test]
+    fn get_set_round_trips_a_simple_path() {
+        let schema = test_schema(vec![Rc::new(test_attr("userName", "string"))]);
+        let handle = SchemaRef::resolve(&schema, "userName").unwrap();
+
+        let mut resource = Value::Object(Map::new());
+        handle.set(&mut resource, Value::String(String::from("bjensen"))).unwrap();
+
+        assert_eq!(handle.get(&resource), Some(&Value::String(String::from("bjensen"))));
+    }
+
+    #[test]
+    fn get_set_round_trips_through_a_value_filter() {
+        let schema = emailsSchema(true);
+        let handle = SchemaRef::resolve(&schema, "emails[type eq \"work\"].value").unwrap();
+
+        let mut resource = Value::Object(Map::new());
+        handle.set(&mut resource, Value::String(String::from("b@example.com"))).unwrap();
+
+        assert_eq!(
+            handle.get(&resource),
+            Some(&Value::String(String::from("b@example.com")))
+        );
+    }
+
+    #[test]
+    fn resolve_rejects_unfiltered_traversal_through_a_multi_valued_attribute() {
+        let schema = emailsSchema(true);
+        assert!(SchemaRef::resolve(&schema, "emails.value").is_none());
+    }
+
+    #[test]
+    fn resolve_allows_unfiltered_traversal_when_not_multi_valued() {
+        let schema = emailsSchema(false);
+        assert!(SchemaRef::resolve(&schema, "emails.value").is_some());
+    }
+
+    #[test]
+    fn resolve_returns_none_instead_of_panicking_on_malformed_brackets() {
+        let schema = emailsSchema(true);
+        assert!(SchemaRef::resolve(&schema, "a]b[c").is_none());
+    }
+
+    #[test]
+    fn set_rejects_readonly_attributes() {
+        let mut id = test_attr("id", "string");
+        id.isReadOnly = true;
+        let schema = test_schema(vec![Rc::new(id)]);
+        let handle = SchemaRef::resolve(&schema, "id").unwrap();
+
+        let mut resource = Value::Object(Map::new());
+        let err = handle.set(&mut resource, Value::String(String::from("1"))).unwrap_err();
+        assert!(matches!(err, ReflectError::ReadOnly(_)));
+    }
+
+    #[test]
+    fn set_rejects_a_type_mismatch() {
+        let schema = test_schema(vec![Rc::new(test_attr("active", "boolean"))]);
+        let handle = SchemaRef::resolve(&schema, "active").unwrap();
+
+        let mut resource = Value::Object(Map::new());
+        let err = handle
+            .set(&mut resource, Value::String(String::from("yes")))
+            .unwrap_err();
+        assert!(matches!(err, ReflectError::TypeMismatch { .. }));
+    }
+}